@@ -1,6 +1,8 @@
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use slatedb::bytes::Bytes;
+use clap::ValueEnum;
+use futures::stream::{self, StreamExt};
 use slatedb::config::WriteOptions;
 use slatedb::{Db, SlateDBError, WriteBatch};
 
@@ -12,7 +14,32 @@ use tracing::error;
 
 // Constants for defaults
 const DEFAULT_BLOCK_SIZE: u64 = 4096; // 4 KiB - Block size is now fixed
-const DEFAULT_DEVICE_SIZE: u64 = 10 * 1024 * 1024 * 1024; // 10 GiB
+pub(crate) const DEFAULT_DEVICE_SIZE: u64 = 10 * 1024 * 1024 * 1024; // 10 GiB
+// How many blocks worth of object-store round-trips may be in flight at once
+pub(crate) const DEFAULT_CONCURRENCY: usize = 16;
+// How many blocks a single delete_range batch covers, so a trim/write_zeroes
+// spanning a huge range is split into several concurrent, bounded-size writes
+// instead of one enormous batch.
+const DELETE_CHUNK_BLOCKS: u64 = 1024;
+
+// Stored block values are tagged with a one-byte prefix so `read_block` knows
+// how to get back to exactly `block_size` bytes, regardless of which
+// compression (if any) was in effect when the block was written.
+const BLOCK_TAG_RAW: u8 = 0;
+const BLOCK_TAG_LZ4: u8 = 1;
+const BLOCK_TAG_ZSTD: u8 = 2;
+// Marks a snapshot pre-image as "this block was sparse (all zero) when the
+// snapshot was taken", distinct from "never touched since the snapshot".
+const BLOCK_TAG_SPARSE: u8 = 3;
+
+/// Per-block compression applied before a value is handed to `WriteBatch::put`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
 
 fn slate_db_error_to_protocol_error(err: SlateDBError) -> ProtocolError {
     match err {
@@ -25,10 +52,38 @@ fn slate_db_error_to_protocol_error(err: SlateDBError) -> ProtocolError {
 
 pub(crate) struct SlateDbDriver {
     db: Db,
+    // Namespaces this export's keys so several exports can share one `Db`
+    // without their block ranges overlapping.
+    export_id: u32,
+    name: String,
     // These must be read from the metadata block
     block_size: u64,
     device_size: AtomicU64,
     read_only: bool,
+    compression: Compression,
+    verify_reads: bool,
+    // Max number of in-flight object-store round-trips for a single read or
+    // delete_range call.
+    concurrency: usize,
+    // Set when this driver is a read-only view of a snapshot rather than the
+    // live export; `export_id` still points at the live export's key range so
+    // unmodified blocks are read straight from it.
+    snapshot: Option<SnapshotView>,
+}
+
+// Identifies which snapshot a read-only `SlateDbDriver` presents. Live
+// drivers leave `snapshot` as `None`.
+struct SnapshotView {
+    snapshot_id: u64,
+}
+
+/// Identifies and sizes one logical device hosted on a shared `Db`.
+#[derive(Debug, Clone)]
+pub(crate) struct ExportConfig {
+    pub(crate) export_id: u32,
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    pub(crate) read_only: bool,
 }
 
 #[derive(Debug, Error)]
@@ -44,9 +99,19 @@ impl SlateDbDriver {
     // Block zero is used to store the device size as a u64
     const RESERVED_BLOCKS: u64 = 8;
     const SIZE_BLOCK: u64 = 0;
-
-    async fn _upsert_device_size(db: &Db, desired_size: u64) -> Result<(), InitError> {
-        let current_size = match db.get(Self::block_to_key(Self::SIZE_BLOCK)).await? {
+    // Block one stores a flags byte (currently just `READ_ONLY_FLAG`) so
+    // per-export flags declared at `serve` time still apply when an export is
+    // reopened from a separate process (e.g. an admin subcommand), instead of
+    // being re-derived (or assumed) at each call site.
+    const FLAGS_BLOCK: u64 = 1;
+    const READ_ONLY_FLAG: u8 = 1 << 0;
+
+    async fn _upsert_device_size(
+        db: &Db,
+        export_id: u32,
+        desired_size: u64,
+    ) -> Result<(), InitError> {
+        let current_size = match db.get(Self::block_to_key_for(export_id, Self::SIZE_BLOCK)).await? {
             Some(data) if data.len() == 8 => Some(u64::from_le_bytes([
                 data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
             ])),
@@ -64,28 +129,29 @@ impl SlateDbDriver {
             match desired_size.cmp(&size) {
                 std::cmp::Ordering::Equal => Ok(()),
                 std::cmp::Ordering::Greater => {
-                    // We cannot shrink the device size, so we produce an
-                    Err(InitError::MetadataFailure(
-                        "Cannot shrink device size".to_string(),
-                    ))
-                }
-                std::cmp::Ordering::Less => {
-                    // If the current size is greater than the desired size, we can
-                    // grow the device by writing the desired size
+                    // Growing just appends; no existing data is disturbed
                     db.put(
-                        Self::block_to_key(Self::SIZE_BLOCK),
+                        Self::block_to_key_for(export_id, Self::SIZE_BLOCK),
                         &desired_size.to_le_bytes(),
                     )
                     .await?;
                     // Return Ok to indicate the size was updated
                     Ok(())
                 }
+                std::cmp::Ordering::Less => {
+                    // Shrinking requires reclaiming every block at or beyond the
+                    // new end first (see `resize`), which this bootstrap helper
+                    // does not do, so refuse rather than silently truncating data.
+                    Err(InitError::MetadataFailure(
+                        "Cannot shrink device size".to_string(),
+                    ))
+                }
             }
         } else {
             // If the current size is None, write out the desired size
             // unconditionally
             db.put(
-                Self::block_to_key(Self::SIZE_BLOCK),
+                Self::block_to_key_for(export_id, Self::SIZE_BLOCK),
                 &desired_size.to_le_bytes(),
             )
             .await?;
@@ -93,17 +159,116 @@ impl SlateDbDriver {
         }
     }
 
-    pub(crate) async fn try_from_db(db: Db) -> std::result::Result<Self, InitError> {
-        Self::_upsert_device_size(&db, DEFAULT_DEVICE_SIZE).await?;
+    // Persist `read_only` as of this `serve` startup, so a process that later
+    // reopens the export via `try_open_existing` (an admin subcommand, which
+    // has no other way to learn the export's declared flags) enforces the
+    // same read-only protection the live server does.
+    async fn _upsert_flags(db: &Db, export_id: u32, read_only: bool) -> Result<(), InitError> {
+        let flags: u8 = if read_only { Self::READ_ONLY_FLAG } else { 0 };
+        db.put(Self::block_to_key_for(export_id, Self::FLAGS_BLOCK), &[flags])
+            .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn try_from_db(
+        db: Db,
+        export: ExportConfig,
+        compression: Compression,
+        verify_reads: bool,
+        concurrency: usize,
+    ) -> std::result::Result<Self, InitError> {
+        Self::_upsert_device_size(&db, export.export_id, export.size).await?;
+        Self::_upsert_flags(&db, export.export_id, export.read_only).await?;
 
         Ok(Self {
             db,
+            export_id: export.export_id,
+            name: export.name,
             block_size: DEFAULT_BLOCK_SIZE, // Block size is now fixed
-            device_size: AtomicU64::new(DEFAULT_DEVICE_SIZE),
-            read_only: false,
+            device_size: AtomicU64::new(export.size),
+            read_only: export.read_only,
+            compression,
+            verify_reads,
+            concurrency: concurrency.max(1),
+            snapshot: None,
         })
     }
 
+    // Open a handle onto an export that's expected to already exist, without
+    // the create-or-grow semantics of `try_from_db`. Used by the snapshot
+    // admin commands, which only need to read metadata for a live export and
+    // must not accidentally resize it.
+    pub(crate) async fn try_open_existing(
+        db: Db,
+        export_id: u32,
+        name: String,
+        compression: Compression,
+        verify_reads: bool,
+        concurrency: usize,
+    ) -> Result<Self, InitError> {
+        let size = match db.get(Self::block_to_key_for(export_id, Self::SIZE_BLOCK)).await? {
+            Some(data) if data.len() == 8 => u64::from_le_bytes([
+                data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+            ]),
+            _ => {
+                return Err(InitError::MetadataFailure(format!(
+                    "Export '{}' does not exist",
+                    name
+                )));
+            }
+        };
+
+        // Exports created before `FLAGS_BLOCK` existed have no flags byte;
+        // treat that as "not read-only" rather than failing to open them.
+        let read_only = match db
+            .get(Self::block_to_key_for(export_id, Self::FLAGS_BLOCK))
+            .await?
+        {
+            Some(data) if !data.is_empty() => data[0] & Self::READ_ONLY_FLAG != 0,
+            _ => false,
+        };
+
+        Ok(Self {
+            db,
+            export_id,
+            name,
+            block_size: DEFAULT_BLOCK_SIZE,
+            device_size: AtomicU64::new(size),
+            read_only,
+            compression,
+            verify_reads,
+            concurrency: concurrency.max(1),
+            snapshot: None,
+        })
+    }
+
+    // Build a read-only view of `snapshot_id`, taken against `live_export_id`.
+    // Reads fall through to the live export's blocks for anything the
+    // snapshot hasn't diverged from yet (see `read_block`).
+    pub(crate) fn from_snapshot(
+        db: Db,
+        name: String,
+        live_export_id: u32,
+        snapshot_id: u64,
+        size: u64,
+        compression: Compression,
+        verify_reads: bool,
+        concurrency: usize,
+    ) -> Self {
+        Self {
+            db,
+            export_id: live_export_id,
+            name,
+            block_size: DEFAULT_BLOCK_SIZE,
+            device_size: AtomicU64::new(size),
+            read_only: true,
+            compression,
+            verify_reads,
+            concurrency: concurrency.max(1),
+            snapshot: Some(SnapshotView { snapshot_id }),
+        }
+    }
+
     // Helper method to check if an address is valid for the device
     fn check_address_valid(&self, address: u64) -> Result<(), ProtocolError> {
         if address % self.block_size != 0 {
@@ -126,39 +291,559 @@ impl SlateDbDriver {
         Ok(())
     }
 
-    fn block_to_key(block: u64) -> [u8; 8] {
-        (block + Self::RESERVED_BLOCKS).to_le_bytes()
+    // Prepending the export id gives each export a disjoint key range within
+    // the shared `Db`, so multiple exports can coexist without colliding.
+    fn block_to_key_for(export_id: u32, block: u64) -> [u8; 12] {
+        let mut key = [0u8; 12];
+        key[..4].copy_from_slice(&export_id.to_le_bytes());
+        key[4..].copy_from_slice(&(block + Self::RESERVED_BLOCKS).to_le_bytes());
+        key
+    }
+
+    fn block_to_key(&self, block: u64) -> [u8; 12] {
+        Self::block_to_key_for(self.export_id, block)
+    }
+
+    // Checksums live in a parallel keyspace: the block's data key prefixed
+    // with a tag byte, so the two never collide regardless of how large the
+    // device grows.
+    const CHECKSUM_KEY_TAG: u8 = 0xFF;
+
+    fn checksum_key(&self, block: u64) -> [u8; 13] {
+        let mut key = [0u8; 13];
+        key[0] = Self::CHECKSUM_KEY_TAG;
+        key[1..].copy_from_slice(&self.block_to_key(block));
+        key
+    }
+
+    // Snapshots get their own tagged keyspaces, one level further removed
+    // than the checksum keyspace, so data, checksum, and per-snapshot
+    // pre-images never collide:
+    //   - SNAPSHOT_REGISTRY_TAG + export_id -> the list of currently-open
+    //     snapshot ids for that export (used by `write`/`delete_range` to
+    //     know which pre-images to preserve before clobbering a block).
+    //   - SNAPSHOT_SIZE_TAG + export_id + snapshot_id -> the live device size
+    //     as of the moment the snapshot was taken, so mounting it later
+    //     exposes the size it actually had (not the live export's current,
+    //     possibly since-shrunk, size).
+    //   - SNAPSHOT_DATA_TAG / SNAPSHOT_CHECKSUM_TAG + snapshot_id + block key
+    //     -> the block's pre-image (and its checksum), lazily copied the
+    //     first time the live block is overwritten or deleted after the
+    //     snapshot was taken.
+    const SNAPSHOT_REGISTRY_TAG: u8 = 0xFC;
+    const SNAPSHOT_SIZE_TAG: u8 = 0xFA;
+    const SNAPSHOT_DATA_TAG: u8 = 0xFE;
+    const SNAPSHOT_CHECKSUM_TAG: u8 = 0xFD;
+
+    fn snapshot_registry_key(export_id: u32) -> [u8; 5] {
+        let mut key = [0u8; 5];
+        key[0] = Self::SNAPSHOT_REGISTRY_TAG;
+        key[1..].copy_from_slice(&export_id.to_le_bytes());
+        key
     }
 
-    async fn read_block(&self, block: u64) -> Result<Option<Bytes>, ProtocolError> {
+    fn snapshot_size_key(export_id: u32, snapshot_id: u64) -> [u8; 13] {
+        let mut key = [0u8; 13];
+        key[0] = Self::SNAPSHOT_SIZE_TAG;
+        key[1..5].copy_from_slice(&export_id.to_le_bytes());
+        key[5..].copy_from_slice(&snapshot_id.to_le_bytes());
+        key
+    }
+
+    fn snapshot_data_key(export_id: u32, snapshot_id: u64, block: u64) -> [u8; 21] {
+        let mut key = [0u8; 21];
+        key[0] = Self::SNAPSHOT_DATA_TAG;
+        key[1..9].copy_from_slice(&snapshot_id.to_le_bytes());
+        key[9..].copy_from_slice(&Self::block_to_key_for(export_id, block));
+        key
+    }
+
+    fn snapshot_checksum_key(export_id: u32, snapshot_id: u64, block: u64) -> [u8; 21] {
+        let mut key = [0u8; 21];
+        key[0] = Self::SNAPSHOT_CHECKSUM_TAG;
+        key[1..9].copy_from_slice(&snapshot_id.to_le_bytes());
+        key[9..].copy_from_slice(&Self::block_to_key_for(export_id, block));
+        key
+    }
+
+    async fn list_open_snapshots(&self, export_id: u32) -> Result<Vec<u64>, ProtocolError> {
+        let data = self
+            .db
+            .get(Self::snapshot_registry_key(export_id))
+            .await
+            .map_err(slate_db_error_to_protocol_error)?;
+
+        Ok(Self::decode_snapshot_ids(data.as_ref().map(|data| data.as_ref())))
+    }
+
+    fn decode_snapshot_ids(data: Option<&[u8]>) -> Vec<u64> {
+        match data {
+            Some(data) => data
+                .chunks_exact(8)
+                .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // `create_snapshot`/`delete_snapshot` both rewrite the whole open-snapshot
+    // registry, so two racing admin invocations (e.g. a scheduled snapshot job
+    // racing a manual one) could read the same starting list and one write
+    // clobber the other. `slatedb`'s `Db` only gives us plain `get`/`put` on
+    // application keys here -- the conditional-put support it documents
+    // (`S3ConditionalPut::ETagMatch` in `main.rs`) is wired into its own
+    // manifest/WAL writes, not exposed as a per-key compare-and-swap we can
+    // call from driver code. So this is a best-effort read-modify-write: it
+    // narrows the race window versus a long-held lock, but two invocations
+    // that both read before either writes can still lose an update. Until
+    // `slatedb` exposes a real conditional put, avoiding that requires
+    // serializing snapshot admin operations for a given export outside this
+    // process (e.g. don't run two snapshot admin commands concurrently).
+    async fn update_snapshot_registry(
+        &self,
+        mutate: impl Fn(Vec<u64>) -> Vec<u64>,
+    ) -> Result<Vec<u64>, ProtocolError> {
+        let registry_key = Self::snapshot_registry_key(self.export_id);
+
+        let before = self
+            .db
+            .get(registry_key)
+            .await
+            .map_err(slate_db_error_to_protocol_error)?;
+
+        let updated = mutate(Self::decode_snapshot_ids(before.as_deref()));
+        let encoded: Vec<u8> = updated.iter().flat_map(|id| id.to_le_bytes()).collect();
+
         self.db
-            .get(Self::block_to_key(block))
+            .put(registry_key, &encoded)
             .await
-            .map_err(slate_db_error_to_protocol_error)
+            .map_err(slate_db_error_to_protocol_error)?;
+
+        Ok(updated)
+    }
+
+    // Before `block` is overwritten or deleted on the live export, copy its
+    // current value (and checksum) into every open snapshot's private
+    // keyspace, unless that snapshot has already captured a pre-image for
+    // this block. A block with no existing value is preserved as
+    // `BLOCK_TAG_SPARSE` so the snapshot can tell "was zero at snapshot time"
+    // apart from "untouched since the snapshot, read through to live".
+    async fn preserve_for_snapshots(
+        &self,
+        batch: &mut WriteBatch,
+        open_snapshots: &[u64],
+        block: u64,
+    ) -> Result<(), ProtocolError> {
+        if open_snapshots.is_empty() {
+            return Ok(());
+        }
+
+        let current_data = self
+            .db
+            .get(self.block_to_key(block))
+            .await
+            .map_err(slate_db_error_to_protocol_error)?;
+        let current_checksum = self
+            .db
+            .get(self.checksum_key(block))
+            .await
+            .map_err(slate_db_error_to_protocol_error)?;
+
+        for &snapshot_id in open_snapshots {
+            let data_key = Self::snapshot_data_key(self.export_id, snapshot_id, block);
+            let already_preserved = self
+                .db
+                .get(data_key)
+                .await
+                .map_err(slate_db_error_to_protocol_error)?
+                .is_some();
+            if already_preserved {
+                continue;
+            }
+
+            match &current_data {
+                Some(data) => {
+                    batch.put(data_key, data);
+                    if let Some(checksum) = &current_checksum {
+                        batch.put(
+                            Self::snapshot_checksum_key(self.export_id, snapshot_id, block),
+                            checksum,
+                        );
+                    }
+                }
+                None => batch.put(data_key, &[BLOCK_TAG_SPARSE]),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a new point-in-time snapshot of this export. Returns the
+    /// snapshot's id, which can later be mounted read-only via
+    /// `from_snapshot` or removed via `delete_snapshot`.
+    pub(crate) async fn create_snapshot(&self) -> Result<u64, ProtocolError> {
+        // Derived from the current time rather than a shared counter, so
+        // allocating an id needs no read-modify-write of its own -- only the
+        // registry update below does (see `update_snapshot_registry` for the
+        // race window that remains there).
+        let snapshot_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ProtocolError::IO)?
+            .as_nanos() as u64;
+
+        // Record the size as of right now, before the snapshot is visible in
+        // the registry, so mounting it later exposes the size it actually
+        // had rather than whatever the live export's size has become since
+        // (e.g. after a `resize` shrink, which would otherwise cut off reads
+        // of pre-images `preserve_for_snapshots` correctly kept around).
+        let size = self.device_size.load(Ordering::Acquire);
+        self.db
+            .put(
+                Self::snapshot_size_key(self.export_id, snapshot_id),
+                &size.to_le_bytes(),
+            )
+            .await
+            .map_err(slate_db_error_to_protocol_error)?;
+
+        self.update_snapshot_registry(move |mut open_snapshots| {
+            if !open_snapshots.contains(&snapshot_id) {
+                open_snapshots.push(snapshot_id);
+            }
+            open_snapshots
+        })
+        .await?;
+
+        Ok(snapshot_id)
+    }
+
+    /// List the ids of snapshots currently open against this export.
+    pub(crate) async fn list_snapshots(&self) -> Result<Vec<u64>, ProtocolError> {
+        self.list_open_snapshots(self.export_id).await
+    }
+
+    /// The live device size as of the moment `snapshot_id` was taken, for use
+    /// when mounting it via `from_snapshot`.
+    pub(crate) async fn snapshot_size(&self, snapshot_id: u64) -> Result<u64, ProtocolError> {
+        match self
+            .db
+            .get(Self::snapshot_size_key(self.export_id, snapshot_id))
+            .await
+            .map_err(slate_db_error_to_protocol_error)?
+        {
+            Some(data) if data.len() == 8 => Ok(u64::from_le_bytes(data[..8].try_into().unwrap())),
+            _ => {
+                error!(
+                    "No recorded size for snapshot {} of export {}",
+                    snapshot_id, self.export_id
+                );
+                Err(ProtocolError::InvalidArgument)
+            }
+        }
+    }
+
+    /// Drop `snapshot_id`, freeing it to stop receiving pre-images. The
+    /// pre-images it already holds are left in place for now (they're no
+    /// longer reachable once the id is removed from the registry, so a
+    /// snapshot export for this id must not be mounted again after this
+    /// call) — reclaiming them would mean scanning the whole device, which a
+    /// future scrub-style background sweep is a better fit for than doing
+    /// inline here.
+    pub(crate) async fn delete_snapshot(&self, snapshot_id: u64) -> Result<(), ProtocolError> {
+        if !self
+            .list_open_snapshots(self.export_id)
+            .await?
+            .contains(&snapshot_id)
+        {
+            return Err(ProtocolError::InvalidArgument);
+        }
+
+        self.update_snapshot_registry(move |mut open_snapshots| {
+            open_snapshots.retain(|id| *id != snapshot_id);
+            open_snapshots
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    // Compress `block` according to `self.compression` and prefix the result
+    // with its tag byte. Incompressible data is stored raw rather than let a
+    // pathological expansion through.
+    fn compress_block(&self, block: &[u8]) -> Vec<u8> {
+        let compressed = match self.compression {
+            Compression::None => None,
+            Compression::Lz4 => Some((BLOCK_TAG_LZ4, lz4_flex::compress_prepend_size(block))),
+            Compression::Zstd => zstd::encode_all(block, 0)
+                .map(|data| (BLOCK_TAG_ZSTD, data))
+                .ok(),
+        };
+
+        match compressed {
+            Some((tag, payload)) if payload.len() < block.len() => {
+                let mut out = Vec::with_capacity(payload.len() + 1);
+                out.push(tag);
+                out.extend_from_slice(&payload);
+                out
+            }
+            _ => {
+                let mut out = Vec::with_capacity(block.len() + 1);
+                out.push(BLOCK_TAG_RAW);
+                out.extend_from_slice(block);
+                out
+            }
+        }
+    }
+
+    // Undo `compress_block`, returning exactly `block_size` bytes.
+    fn decompress_block(&self, data: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        let (tag, payload) = data.split_first().ok_or_else(|| {
+            error!("Stored block value is missing its compression tag byte");
+            ProtocolError::IO
+        })?;
+
+        let block = match *tag {
+            BLOCK_TAG_RAW => payload.to_vec(),
+            BLOCK_TAG_LZ4 => lz4_flex::decompress_size_prepended(payload).map_err(|e| {
+                error!("Failed to decompress lz4 block: {}", e);
+                ProtocolError::IO
+            })?,
+            BLOCK_TAG_ZSTD => zstd::decode_all(payload).map_err(|e| {
+                error!("Failed to decompress zstd block: {}", e);
+                ProtocolError::IO
+            })?,
+            other => {
+                error!("Unknown block compression tag {}", other);
+                return Err(ProtocolError::IO);
+            }
+        };
+
+        if block.len() != self.block_size as usize {
+            error!(
+                "Decompressed block length {} does not match block size {}",
+                block.len(),
+                self.block_size
+            );
+            return Err(ProtocolError::InvalidArgument);
+        }
+
+        Ok(block)
+    }
+
+    async fn read_block(&self, block: u64) -> Result<Option<Vec<u8>>, ProtocolError> {
+        let (data, checksum) = match &self.snapshot {
+            Some(snapshot) => {
+                let preserved = self
+                    .db
+                    .get(Self::snapshot_data_key(self.export_id, snapshot.snapshot_id, block))
+                    .await
+                    .map_err(slate_db_error_to_protocol_error)?;
+
+                match preserved {
+                    // The live block was overwritten/deleted since the
+                    // snapshot was taken; the pre-image is authoritative.
+                    Some(data) if data.first() == Some(&BLOCK_TAG_SPARSE) => (None, None),
+                    Some(data) => {
+                        let checksum = self
+                            .db
+                            .get(Self::snapshot_checksum_key(
+                                self.export_id,
+                                snapshot.snapshot_id,
+                                block,
+                            ))
+                            .await
+                            .map_err(slate_db_error_to_protocol_error)?;
+                        (Some(data), checksum)
+                    }
+                    // Untouched since the snapshot: identical to the live block.
+                    None => {
+                        let data = self
+                            .db
+                            .get(self.block_to_key(block))
+                            .await
+                            .map_err(slate_db_error_to_protocol_error)?;
+                        let checksum = self
+                            .db
+                            .get(self.checksum_key(block))
+                            .await
+                            .map_err(slate_db_error_to_protocol_error)?;
+                        (data, checksum)
+                    }
+                }
+            }
+            None => {
+                let data = self
+                    .db
+                    .get(self.block_to_key(block))
+                    .await
+                    .map_err(slate_db_error_to_protocol_error)?;
+                let checksum = self
+                    .db
+                    .get(self.checksum_key(block))
+                    .await
+                    .map_err(slate_db_error_to_protocol_error)?;
+                (data, checksum)
+            }
+        };
+
+        let Some(data) = data else {
+            // Sparse zeros; there's no checksum to verify.
+            return Ok(None);
+        };
+
+        let block_data = self.decompress_block(&data)?;
+
+        if self.verify_reads {
+            self.verify_checksum_against(block, &block_data, checksum)?;
+        }
+
+        Ok(Some(block_data))
+    }
+
+    fn verify_checksum_against(
+        &self,
+        block: u64,
+        data: &[u8],
+        checksum: Option<impl AsRef<[u8]>>,
+    ) -> Result<(), ProtocolError> {
+        match checksum.as_ref().map(|c| c.as_ref()) {
+            Some(expected) if expected.len() == 4 => {
+                let expected_crc =
+                    u32::from_le_bytes([expected[0], expected[1], expected[2], expected[3]]);
+                let actual_crc = crc32fast::hash(data);
+                if actual_crc != expected_crc {
+                    error!(
+                        "Checksum mismatch for block {}: expected {:#010x}, got {:#010x} \
+                         (likely bit-rot or a corrupt read from the object store)",
+                        block, expected_crc, actual_crc
+                    );
+                    return Err(ProtocolError::IO);
+                }
+                Ok(())
+            }
+            Some(_) => {
+                error!("Checksum metadata for block {} is corrupted", block);
+                Err(ProtocolError::IO)
+            }
+            // No checksum was ever recorded for this block; nothing to verify against.
+            None => Ok(()),
+        }
+    }
+
+    /// Walk every allocated block and report checksum failures. Intended to
+    /// be run periodically as a background task; sparse blocks are skipped
+    /// since they have no checksum.
+    pub(crate) async fn scrub(&self) -> usize {
+        let device_size = self.device_size.load(Ordering::Acquire);
+        let total_blocks = device_size / self.block_size;
+        let mut bad_blocks = 0;
+
+        for logical_block in 0..total_blocks {
+            let block = Self::RESERVED_BLOCKS + logical_block;
+            if let Err(e) = self.read_block(block).await {
+                error!(
+                    "Scrub found a bad block at offset {}: {:?}",
+                    logical_block * self.block_size,
+                    e
+                );
+                bad_blocks += 1;
+            }
+        }
+
+        bad_blocks
     }
 
     // Because SlateDB is sparse, there is no functional difference between writing
     // zeros and trimming a range
+    // A huge trim/write_zeroes range is split into chunks so no single batch
+    // grows unbounded, and the chunks are flushed with the same bounded
+    // concurrency as `read` to cap object-store fan-out.
     async fn delete_range(
         &self,
         start_block: u64,
         end_block: u64,
         await_durable: bool,
     ) -> Result<(), ProtocolError> {
-        let mut batch = WriteBatch::new();
+        let write_options = WriteOptions { await_durable };
+        // Preserve pre-images for any open snapshots before the blocks below
+        // are reclaimed, so `delete_range` never loses data a snapshot still
+        // references.
+        let open_snapshots = self.list_open_snapshots(self.export_id).await?;
+
+        let chunk_starts = (start_block..end_block).step_by(DELETE_CHUNK_BLOCKS as usize);
+
+        let mut deletes = stream::iter(chunk_starts)
+            .map(|chunk_start| {
+                let chunk_end = (chunk_start + DELETE_CHUNK_BLOCKS).min(end_block);
+                let write_options = &write_options;
+                let open_snapshots = &open_snapshots;
+                async move {
+                    let mut batch = WriteBatch::new();
+                    for block in chunk_start..chunk_end {
+                        self.preserve_for_snapshots(&mut batch, open_snapshots, block)
+                            .await?;
+                        batch.delete(self.block_to_key(block));
+                        batch.delete(self.checksum_key(block));
+                    }
+                    self.db
+                        .write_with_options(batch, write_options)
+                        .await
+                        .map_err(slate_db_error_to_protocol_error)
+                }
+            })
+            .buffer_unordered(self.concurrency);
 
-        for block in start_block..end_block {
-            batch.delete(Self::block_to_key(block));
+        while let Some(result) = deletes.next().await {
+            result?;
         }
 
-        let write_options = WriteOptions {
-            await_durable: await_durable,
-        };
+        Ok(())
+    }
 
-        self.db
-            .write_with_options(batch, &write_options)
-            .await
-            .map_err(slate_db_error_to_protocol_error)
+    // Grow or shrink the device at runtime. The new size is durably persisted
+    // to `SIZE_BLOCK` before `device_size` is updated, so a client that
+    // reconnects (or races a resize) always observes the authoritative,
+    // already-persisted size rather than one the server merely intends to commit.
+    pub(crate) async fn resize(&self, new_size: u64) -> Result<(), ProtocolError> {
+        if self.read_only {
+            return Err(ProtocolError::CommandNotSupported);
+        }
+        if new_size % self.block_size != 0 {
+            error!(
+                "Requested size {} is not aligned to block size {}",
+                new_size, self.block_size
+            );
+            return Err(ProtocolError::InvalidArgument);
+        }
+
+        let current_size = self.device_size.load(Ordering::Acquire);
+
+        match new_size.cmp(&current_size) {
+            std::cmp::Ordering::Equal => Ok(()),
+            std::cmp::Ordering::Greater => {
+                // Growing just appends; existing blocks are untouched, so it's
+                // safe to persist the new size directly.
+                self.db
+                    .put(self.block_to_key(Self::SIZE_BLOCK), &new_size.to_le_bytes())
+                    .await
+                    .map_err(slate_db_error_to_protocol_error)?;
+                self.device_size.store(new_size, Ordering::Release);
+                Ok(())
+            }
+            std::cmp::Ordering::Less => {
+                // Reclaim every block at or beyond the new end first, so stale
+                // data can't resurface if the device is later grown again.
+                let start_block = new_size / self.block_size + Self::RESERVED_BLOCKS;
+                let end_block = current_size / self.block_size + Self::RESERVED_BLOCKS;
+                self.delete_range(start_block, end_block, true).await?;
+
+                self.db
+                    .put(self.block_to_key(Self::SIZE_BLOCK), &new_size.to_le_bytes())
+                    .await
+                    .map_err(slate_db_error_to_protocol_error)?;
+                self.device_size.store(new_size, Ordering::Release);
+                Ok(())
+            }
+        }
     }
 }
 
@@ -169,7 +854,7 @@ impl NbdDriver for SlateDbDriver {
             | ServerFeatures::SEND_TRIM
             | ServerFeatures::SEND_WRITE_ZEROES
             | ServerFeatures::CAN_MULTI_CONN
-        // Todo: implement resize. Shouldn't be too bad
+            | ServerFeatures::SEND_RESIZE
     }
 
     async fn get_read_only(&self) -> Result<bool, OptionReplyError> {
@@ -184,15 +869,13 @@ impl NbdDriver for SlateDbDriver {
     }
 
     async fn get_canonical_name(&self) -> Result<String, OptionReplyError> {
-        // SlateDB does not support multiple devices, so we return the device name as is
-        Ok("SlateDB Device".to_string())
+        Ok(self.name.clone())
     }
 
     async fn get_description(&self) -> Result<String, OptionReplyError> {
-        // SlateDB does not support descriptions, so we return the device name as the description
         Ok(format!(
-            "SlateDB device with block size {} bytes",
-            self.block_size
+            "SlateDB export '{}' with block size {} bytes",
+            self.name, self.block_size
         ))
     }
 
@@ -225,20 +908,18 @@ impl NbdDriver for SlateDbDriver {
         //     "Handling read command: start_block={}, end_block={}, length={}",
         //     start_block, end_block, length
         // );
-        // Consider FuturesOrdered
-        for block in start_block..end_block {
-            match self.read_block(block).await? {
+        // Issue up to `concurrency` block reads at once, hiding object-store
+        // latency, while `buffered` keeps results in block order.
+        let mut reads = stream::iter(start_block..end_block)
+            .map(|block| self.read_block(block))
+            .buffered(self.concurrency);
+
+        while let Some(result) = reads.next().await {
+            match result? {
                 Some(data) => {
-                    // write
-                    if data.len() != self.block_size as usize {
-                        error!(
-                            "Data length {} does not match block size {}",
-                            data.len(),
-                            self.block_size
-                        );
-                        return Err(ProtocolError::InvalidArgument);
-                    }
-                    buff.extend(data.as_ref());
+                    // `read_block` already decompressed this back to exactly
+                    // `block_size` bytes (or returned an error).
+                    buff.extend(data);
                 }
                 // write zeros
                 None => {
@@ -272,6 +953,10 @@ impl NbdDriver for SlateDbDriver {
         offset: u64,
         data: Vec<u8>,
     ) -> Result<(), ProtocolError> {
+        if self.read_only {
+            return Err(ProtocolError::CommandNotSupported);
+        }
+
         // Ensure offset is valid
         self.check_address_valid(offset)?;
 
@@ -286,13 +971,22 @@ impl NbdDriver for SlateDbDriver {
         }
 
         let start_block = offset / self.block_size + Self::RESERVED_BLOCKS;
+        let open_snapshots = self.list_open_snapshots(self.export_id).await?;
 
         let mut batch = WriteBatch::new();
 
         for (chunk_offset, chunk) in data.chunks(self.block_size as usize).enumerate() {
-            let key = Self::block_to_key(start_block + chunk_offset as u64);
-
-            batch.put(key, &chunk);
+            let block = start_block + chunk_offset as u64;
+            self.preserve_for_snapshots(&mut batch, &open_snapshots, block)
+                .await?;
+            let key = self.block_to_key(block);
+            let value = self.compress_block(chunk);
+
+            batch.put(key, &value);
+            // Checksum the uncompressed block so verification is independent
+            // of which compression (if any) was used to store it, and batch
+            // it atomically with the data put.
+            batch.put(self.checksum_key(block), &crc32fast::hash(chunk).to_le_bytes());
         }
         let write_options = WriteOptions {
             await_durable: flags.contains(CommandFlags::FUA),
@@ -317,6 +1011,10 @@ impl NbdDriver for SlateDbDriver {
         offset: u64,
         length: u32,
     ) -> Result<(), ProtocolError> {
+        if self.read_only {
+            return Err(ProtocolError::CommandNotSupported);
+        }
+
         // Ensure offset is valid
         self.check_address_valid(offset)?;
 
@@ -336,6 +1034,10 @@ impl NbdDriver for SlateDbDriver {
         offset: u64,
         length: u32,
     ) -> Result<(), ProtocolError> {
+        if self.read_only {
+            return Err(ProtocolError::CommandNotSupported);
+        }
+
         // TODO Handle fast zero flag
         // Ensure offset is valid
         self.check_address_valid(offset)?;
@@ -364,21 +1066,160 @@ impl NbdDriver for SlateDbDriver {
 
 #[cfg(test)]
 mod tests {
-    use crate::driver_slatedb::SlateDbDriver;
+    use crate::driver_slatedb::{
+        BLOCK_TAG_RAW, Compression, DEFAULT_BLOCK_SIZE, DELETE_CHUNK_BLOCKS, ExportConfig,
+        SlateDbDriver, DEFAULT_CONCURRENCY, DEFAULT_DEVICE_SIZE,
+    };
     use slatedb::Db;
     use slatedb::object_store::{ObjectStore, memory::InMemory};
     use std::sync::Arc;
+    use std::sync::atomic::Ordering;
     use tokio_nbd::device::NbdDriver;
+    use tokio_nbd::errors::ProtocolError;
     use tokio_nbd::flags::CommandFlags;
 
+    // Helper to open a fresh in-memory `Db` for a test, so each test gets its
+    // own isolated key space without re-pasting the object-store boilerplate.
+    async fn open_test_db(path: &str) -> Db {
+        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        Db::open(path, object_store)
+            .await
+            .expect("failed to create test kv store")
+    }
+
     // Helper function to create an in-memory SlateDbDriver for testing
     async fn create_test_driver() -> SlateDbDriver {
-        let object_store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
-        let kv_store = Db::open("/tmp/test_kv_store", object_store)
+        create_test_driver_with_compression(Compression::None).await
+    }
+
+    async fn create_test_driver_with_compression(compression: Compression) -> SlateDbDriver {
+        let kv_store = open_test_db("/tmp/test_kv_store").await;
+        let export = ExportConfig {
+            export_id: 0,
+            name: "test".to_string(),
+            size: DEFAULT_DEVICE_SIZE,
+            read_only: false,
+        };
+        let driver =
+            SlateDbDriver::try_from_db(kv_store, export, compression, true, DEFAULT_CONCURRENCY)
+                .await
+                .unwrap();
+        driver
+    }
+
+    #[tokio::test]
+    async fn test_compress_block_round_trips_through_lz4_and_zstd() {
+        // Highly compressible so both codecs actually shrink it, rather than
+        // falling back to the raw tag.
+        let block = vec![0x00; DEFAULT_BLOCK_SIZE as usize];
+
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let driver = create_test_driver_with_compression(compression).await;
+            let compressed = driver.compress_block(&block);
+            assert!(
+                compressed.len() < block.len(),
+                "{:?} should shrink a highly compressible block",
+                compression
+            );
+            let decompressed = driver.decompress_block(&compressed).unwrap();
+            assert_eq!(decompressed, block);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compress_block_falls_back_to_raw_for_incompressible_data() {
+        let driver = create_test_driver_with_compression(Compression::Lz4).await;
+
+        // Pseudo-random bytes that lz4 can't meaningfully shrink.
+        let mut state: u32 = 0x9E37_79B9;
+        let incompressible: Vec<u8> = (0..DEFAULT_BLOCK_SIZE)
+            .map(|_| {
+                // xorshift32
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 24) as u8
+            })
+            .collect();
+
+        let compressed = driver.compress_block(&incompressible);
+        assert_eq!(
+            compressed.first(),
+            Some(&BLOCK_TAG_RAW),
+            "incompressible data should be stored with the raw tag rather than an expanded payload"
+        );
+        assert_eq!(compressed.len(), incompressible.len() + 1);
+
+        let decompressed = driver.decompress_block(&compressed).unwrap();
+        assert_eq!(decompressed, incompressible);
+    }
+
+    #[tokio::test]
+    async fn test_read_detects_corrupted_checksum() {
+        let driver = create_test_driver().await;
+        let data = vec![0x7a; 4096];
+        driver
+            .write(CommandFlags::empty(), 0, data.clone())
             .await
-            .expect("failed to create test kv store");
-        let driver = SlateDbDriver::try_from_db(kv_store).await.unwrap();
+            .unwrap();
+
+        // `write` numbers its first data block as `RESERVED_BLOCKS`, so use
+        // the same value here to land on the checksum key it actually wrote.
+        let block = SlateDbDriver::RESERVED_BLOCKS;
         driver
+            .db
+            .put(driver.checksum_key(block), &0xdeadbeefu32.to_le_bytes())
+            .await
+            .unwrap();
+
+        let err = driver
+            .read(CommandFlags::empty(), 0, 4096)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ProtocolError::IO),
+            "a checksum mismatch should surface as ProtocolError::IO, got {:?}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_detects_corrupted_block_data() {
+        let driver = create_test_driver().await;
+        let data = vec![0x11; 4096];
+        driver
+            .write(CommandFlags::empty(), 0, data.clone())
+            .await
+            .unwrap();
+
+        let block = SlateDbDriver::RESERVED_BLOCKS;
+        let mut stored = driver
+            .db
+            .get(driver.block_to_key(block))
+            .await
+            .unwrap()
+            .unwrap()
+            .as_ref()
+            .to_vec();
+        // Flip a bit in the stored payload, simulating bit-rot in the
+        // object store, while leaving the recorded checksum untouched.
+        let last = stored.len() - 1;
+        stored[last] ^= 0xff;
+        driver
+            .db
+            .put(driver.block_to_key(block), &stored)
+            .await
+            .unwrap();
+
+        let err = driver
+            .read(CommandFlags::empty(), 0, 4096)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ProtocolError::IO),
+            "corrupted block data should surface as ProtocolError::IO, got {:?}",
+            err
+        );
     }
 
     // The original bug was related to buffer length not matching requested length
@@ -587,4 +1428,351 @@ mod tests {
             "Read data should match the expected segment"
         );
     }
+
+    #[tokio::test]
+    async fn test_read_preserves_order_under_concurrency() {
+        // Reads are now issued concurrently (bounded by `concurrency`), so
+        // this verifies the buffered stream still returns blocks in order.
+        let driver = create_test_driver().await;
+
+        let block_count = 32;
+        let mut data = Vec::with_capacity(block_count * 4096);
+        for block in 0..block_count {
+            data.extend(vec![block as u8; 4096]);
+        }
+        driver
+            .write(CommandFlags::empty(), 0, data.clone())
+            .await
+            .unwrap();
+
+        let read_data = driver
+            .read(CommandFlags::empty(), 0, data.len() as u32)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read_data, data,
+            "Blocks read concurrently should still come back in order"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_sees_pre_image_after_live_overwrite() {
+        let kv_store = open_test_db("/tmp/test_kv_store_snapshot").await;
+        let export = ExportConfig {
+            export_id: 0,
+            name: "live".to_string(),
+            size: DEFAULT_DEVICE_SIZE,
+            read_only: false,
+        };
+        let live = SlateDbDriver::try_from_db(
+            kv_store.clone(),
+            export,
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+
+        let before = vec![0x11; 4096];
+        live.write(CommandFlags::empty(), 0, before.clone())
+            .await
+            .unwrap();
+
+        let snapshot_id = live.create_snapshot().await.unwrap();
+        assert_eq!(live.list_snapshots().await.unwrap(), vec![snapshot_id]);
+
+        let after = vec![0x22; 4096];
+        live.write(CommandFlags::empty(), 0, after.clone())
+            .await
+            .unwrap();
+
+        let snapshot = SlateDbDriver::from_snapshot(
+            kv_store,
+            "live@snapshot".to_string(),
+            0,
+            snapshot_id,
+            DEFAULT_DEVICE_SIZE,
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        );
+
+        let snapshot_read = snapshot.read(CommandFlags::empty(), 0, 4096).await.unwrap();
+        assert_eq!(
+            snapshot_read, before,
+            "Snapshot should still see the block as it was when the snapshot was taken"
+        );
+
+        let live_read = live.read(CommandFlags::empty(), 0, 4096).await.unwrap();
+        assert_eq!(
+            live_read, after,
+            "Live export should see the overwrite made after the snapshot"
+        );
+
+        assert!(
+            snapshot
+                .write(CommandFlags::empty(), 0, vec![0x33; 4096])
+                .await
+                .is_err(),
+            "Writes to a snapshot export must be rejected"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_size_is_recorded_at_snapshot_time_not_mount_time() {
+        let kv_store = open_test_db("/tmp/test_kv_store_snapshot_size").await;
+        let live = SlateDbDriver::try_from_db(
+            kv_store.clone(),
+            ExportConfig {
+                export_id: 0,
+                name: "live".to_string(),
+                size: DEFAULT_DEVICE_SIZE,
+                read_only: false,
+            },
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+
+        let tail_offset = DEFAULT_DEVICE_SIZE - 4096;
+        let tail_data = vec![0x44; 4096];
+        live.write(CommandFlags::empty(), tail_offset, tail_data.clone())
+            .await
+            .unwrap();
+
+        let snapshot_id = live.create_snapshot().await.unwrap();
+        assert_eq!(
+            live.snapshot_size(snapshot_id).await.unwrap(),
+            DEFAULT_DEVICE_SIZE
+        );
+
+        // Shrink the live export out from under the snapshot; `resize`
+        // preserves pre-images of the reclaimed tail via
+        // `preserve_for_snapshots` before deleting them.
+        live.resize(DEFAULT_DEVICE_SIZE / 2).await.unwrap();
+
+        let snapshot_size = live.snapshot_size(snapshot_id).await.unwrap();
+        let snapshot = SlateDbDriver::from_snapshot(
+            kv_store,
+            "live@snapshot".to_string(),
+            0,
+            snapshot_id,
+            snapshot_size,
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        );
+
+        let read_tail = snapshot
+            .read(CommandFlags::empty(), tail_offset, 4096)
+            .await
+            .unwrap();
+        assert_eq!(
+            read_tail, tail_data,
+            "mounting a snapshot after the live export shrank should still expose its tail"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_try_open_existing_honors_persisted_read_only_flag() {
+        let kv_store = open_test_db("/tmp/test_kv_store_read_only_flag").await;
+
+        SlateDbDriver::try_from_db(
+            kv_store.clone(),
+            ExportConfig {
+                export_id: 0,
+                name: "ro".to_string(),
+                size: DEFAULT_DEVICE_SIZE,
+                read_only: true,
+            },
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+
+        // Simulates an admin subcommand reopening the export from a separate
+        // process invocation, which has no other way to learn it's read-only.
+        let reopened = SlateDbDriver::try_open_existing(
+            kv_store,
+            0,
+            "ro".to_string(),
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            reopened.resize(DEFAULT_DEVICE_SIZE + 4096).await.is_err(),
+            "an export declared read-only should stay read-only when reopened elsewhere"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exports_sharing_a_db_stay_isolated() {
+        let kv_store = open_test_db("/tmp/test_kv_store_multi_export").await;
+
+        let export_a = SlateDbDriver::try_from_db(
+            kv_store.clone(),
+            ExportConfig {
+                export_id: 1,
+                name: "a".to_string(),
+                size: DEFAULT_DEVICE_SIZE,
+                read_only: false,
+            },
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+        let export_b = SlateDbDriver::try_from_db(
+            kv_store,
+            ExportConfig {
+                export_id: 2,
+                name: "b".to_string(),
+                size: DEFAULT_DEVICE_SIZE,
+                read_only: false,
+            },
+            Compression::None,
+            true,
+            DEFAULT_CONCURRENCY,
+        )
+        .await
+        .unwrap();
+
+        export_a
+            .write(CommandFlags::empty(), 0, vec![0xaa; 4096])
+            .await
+            .unwrap();
+        export_b
+            .write(CommandFlags::empty(), 0, vec![0xbb; 4096])
+            .await
+            .unwrap();
+
+        let read_a = export_a.read(CommandFlags::empty(), 0, 4096).await.unwrap();
+        let read_b = export_b.read(CommandFlags::empty(), 0, 4096).await.unwrap();
+
+        assert_eq!(
+            read_a,
+            vec![0xaa; 4096],
+            "export a's data should be unaffected by export b's write"
+        );
+        assert_eq!(
+            read_b,
+            vec![0xbb; 4096],
+            "export b's data should be unaffected by export a's write"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resize_grows_and_shrinks_device() {
+        let driver = create_test_driver().await;
+
+        let original_size = driver.get_device_size().load(Ordering::Acquire);
+        let grown_size = original_size + 4096;
+        driver.resize(grown_size).await.unwrap();
+        assert_eq!(driver.get_device_size().load(Ordering::Acquire), grown_size);
+
+        // Writing into the newly grown region should now succeed.
+        let data = vec![0x55; 4096];
+        driver
+            .write(CommandFlags::empty(), original_size, data.clone())
+            .await
+            .unwrap();
+        let read_data = driver
+            .read(CommandFlags::empty(), original_size, 4096)
+            .await
+            .unwrap();
+        assert_eq!(read_data, data);
+
+        driver.resize(original_size).await.unwrap();
+        assert_eq!(
+            driver.get_device_size().load(Ordering::Acquire),
+            original_size
+        );
+
+        // The reclaimed region is out of bounds again.
+        assert!(
+            driver
+                .read(CommandFlags::empty(), original_size, 4096)
+                .await
+                .is_err(),
+            "Reading beyond a shrunk device should be rejected"
+        );
+    }
+
+    // `delete_range` splits large trims into `DELETE_CHUNK_BLOCKS`-sized
+    // batches; this pins down that blocks on both sides of a chunk boundary
+    // are cleared, not just the blocks within the first chunk.
+    #[tokio::test]
+    async fn test_trim_spanning_chunk_boundary_clears_both_chunks() {
+        let driver = create_test_driver().await;
+
+        // Cover more than one DELETE_CHUNK_BLOCKS (1024 blocks) worth of data
+        // so the trim is split across at least two chunks.
+        let block_count = DELETE_CHUNK_BLOCKS + 1;
+        let length = (block_count * DEFAULT_BLOCK_SIZE) as u32;
+        let data = vec![0x7e; length as usize];
+        driver
+            .write(CommandFlags::empty(), 0, data.clone())
+            .await
+            .unwrap();
+
+        driver
+            .trim(CommandFlags::empty(), 0, length)
+            .await
+            .unwrap();
+
+        // The last block of the first chunk and the first block of the
+        // second chunk straddle the boundary `delete_range` chunks on.
+        let boundary_offset = (DELETE_CHUNK_BLOCKS - 1) * DEFAULT_BLOCK_SIZE;
+        let around_boundary = driver
+            .read(CommandFlags::empty(), boundary_offset, 2 * DEFAULT_BLOCK_SIZE as u32)
+            .await
+            .unwrap();
+        assert_eq!(
+            around_boundary,
+            vec![0; 2 * DEFAULT_BLOCK_SIZE as usize],
+            "Blocks on both sides of the chunk boundary should read back as zero after a trim"
+        );
+
+        // And the whole trimmed range should be zero, not just the boundary.
+        let whole_range = driver.read(CommandFlags::empty(), 0, length).await.unwrap();
+        assert_eq!(
+            whole_range,
+            vec![0; length as usize],
+            "Entire trimmed range should read back as zero"
+        );
+    }
+
+    // Pins down the grow-vs-shrink semantics of `_upsert_device_size` after a
+    // prior bug had the `Greater`/`Less` arms swapped, silently turning every
+    // restart-time size bump into a rejected shrink (and vice versa).
+    #[tokio::test]
+    async fn test_upsert_device_size_distinguishes_grow_from_shrink() {
+        let kv_store = open_test_db("/tmp/test_kv_store_upsert_size").await;
+
+        SlateDbDriver::_upsert_device_size(&kv_store, 0, DEFAULT_DEVICE_SIZE)
+            .await
+            .unwrap();
+
+        SlateDbDriver::_upsert_device_size(&kv_store, 0, DEFAULT_DEVICE_SIZE + 4096)
+            .await
+            .expect("growing the device size should be allowed");
+
+        assert!(
+            SlateDbDriver::_upsert_device_size(&kv_store, 0, DEFAULT_DEVICE_SIZE)
+                .await
+                .is_err(),
+            "shrinking the device size should be rejected"
+        );
+    }
 }
@@ -2,108 +2,491 @@
 
 mod driver_slatedb;
 
-use crate::driver_slatedb::SlateDbDriver;
+use crate::driver_slatedb::{
+    Compression, ExportConfig, SlateDbDriver, DEFAULT_CONCURRENCY, DEFAULT_DEVICE_SIZE,
+};
 use object_store::ObjectStore;
 use object_store::aws::S3ConditionalPut;
 use slatedb::{Db, Settings};
+use std::str::FromStr;
 use std::sync::Arc;
+use tokio_nbd::device::NbdDriver;
 use tokio_nbd::server::NbdServerBuilder;
 use tracing::{debug, error, info};
 use tracing_subscriber::{EnvFilter, fmt};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+/// Which object-store backend to persist blocks to, and its credentials.
+///
+/// Whichever backend is chosen must support conditional PUT (compare-and-swap
+/// on write) — SlateDB relies on that guarantee for its manifest/WAL. S3 gets
+/// it via `S3ConditionalPut::ETagMatch` below; GCS and Azure provide the
+/// equivalent natively; the local-filesystem and in-memory backends (handy
+/// for running without any cloud credentials, just like the in-memory store
+/// already used in the driver's unit tests) support it out of the box too.
+#[derive(Subcommand, Debug, Clone)]
+enum Backend {
+    /// Amazon S3, or an S3-compatible endpoint
+    S3 {
+        #[arg(long, env = "AWS_ENDPOINT")]
+        s3_endpoint: String,
+
+        #[arg(long, env = "AWS_ALLOW_HTTP", default_value_t = false)]
+        s3_allow_http: bool,
+
+        #[arg(long, env = "AWS_ACCESS_KEY_ID")]
+        s3_access_key_id: String,
+
+        // Pretty sure this should always be an environment variable and you shouldn't
+        // be passing sensitive keys are as CLI arguments
+        #[arg(long, env = "AWS_SECRET_ACCESS_KEY")]
+        s3_secret_access_key: String,
+
+        #[arg(long, env = "AWS_BUCKET_NAME")]
+        s3_bucket_name: String,
+    },
+    /// Google Cloud Storage
+    Gcs {
+        #[arg(long, env = "GOOGLE_SERVICE_ACCOUNT")]
+        gcs_service_account_path: String,
+
+        #[arg(long, env = "GOOGLE_BUCKET_NAME")]
+        gcs_bucket_name: String,
+    },
+    /// Azure Blob Storage
+    Azure {
+        #[arg(long, env = "AZURE_STORAGE_ACCOUNT_NAME")]
+        azure_account_name: String,
+
+        #[arg(long, env = "AZURE_STORAGE_ACCOUNT_KEY")]
+        azure_account_key: String,
+
+        #[arg(long, env = "AZURE_CONTAINER_NAME")]
+        azure_container_name: String,
+    },
+    /// A plain directory on local disk; no cloud credentials needed
+    LocalFilesystem {
+        #[arg(long, default_value = "./slatedb-nbd-data")]
+        path: String,
+    },
+    /// Ephemeral in-process storage; data is lost when the process exits.
+    /// Useful for testing or embedded use.
+    InMemory,
+}
+
+fn build_object_store(backend: &Backend) -> std::io::Result<Arc<dyn ObjectStore>> {
+    match backend {
+        Backend::S3 {
+            s3_endpoint,
+            s3_allow_http,
+            s3_access_key_id,
+            s3_secret_access_key,
+            s3_bucket_name,
+        } => Ok(Arc::new(
+            object_store::aws::AmazonS3Builder::new()
+                // These will be different if you are using real AWS
+                .with_allow_http(*s3_allow_http)
+                .with_endpoint(s3_endpoint)
+                .with_access_key_id(s3_access_key_id)
+                .with_secret_access_key(s3_secret_access_key)
+                .with_bucket_name(s3_bucket_name)
+                .with_conditional_put(S3ConditionalPut::ETagMatch)
+                .build()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+        )),
+        Backend::Gcs {
+            gcs_service_account_path,
+            gcs_bucket_name,
+        } => Ok(Arc::new(
+            object_store::gcp::GoogleCloudStorageBuilder::new()
+                .with_service_account_path(gcs_service_account_path)
+                .with_bucket_name(gcs_bucket_name)
+                .build()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+        )),
+        Backend::Azure {
+            azure_account_name,
+            azure_account_key,
+            azure_container_name,
+        } => Ok(Arc::new(
+            object_store::azure::MicrosoftAzureBuilder::new()
+                .with_account(azure_account_name)
+                .with_access_key(azure_account_key)
+                .with_container_name(azure_container_name)
+                .build()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+        )),
+        Backend::LocalFilesystem { path } => {
+            std::fs::create_dir_all(path)?;
+            Ok(Arc::new(
+                object_store::local::LocalFileSystem::new_with_prefix(path)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+            ))
+        }
+        Backend::InMemory => Ok(Arc::new(object_store::memory::InMemory::new())),
+    }
+}
+
+/// One `--export name:size_bytes[:read_only]` CLI argument
+#[derive(Debug, Clone)]
+struct ExportArg {
+    name: String,
+    size: u64,
+    read_only: bool,
+}
+
+impl FromStr for ExportArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or("export is missing a name")?
+            .to_string();
+        let size = parts
+            .next()
+            .ok_or("export is missing a size in bytes")?
+            .parse()
+            .map_err(|e| format!("invalid export size: {e}"))?;
+        let read_only = match parts.next() {
+            Some(flag) => flag
+                .parse()
+                .map_err(|e| format!("invalid export read-only flag: {e}"))?,
+            None => false,
+        };
+
+        Ok(ExportArg {
+            name,
+            size,
+            read_only,
+        })
+    }
+}
+
+/// One `--mount-snapshot snapshot_id:export_name` CLI argument
+#[derive(Debug, Clone)]
+struct MountSnapshotArg {
+    snapshot_id: u64,
+    export_name: String,
+}
+
+impl FromStr for MountSnapshotArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, name) = s
+            .split_once(':')
+            .ok_or("snapshot mount is missing ':export_name'")?;
+        Ok(MountSnapshotArg {
+            snapshot_id: id.parse().map_err(|e| format!("invalid snapshot id: {e}"))?,
+            export_name: name.to_string(),
+        })
+    }
+}
+
+// Export ids are derived from the export name rather than from CLI
+// declaration order, so the `snapshot` admin subcommands (run as separate,
+// later invocations of this binary) can resolve the same export id the
+// `serve` process used without any extra coordination.
+fn export_id_for_name(name: &str) -> u32 {
+    crc32fast::hash(name.as_bytes())
+}
 
-/// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-struct Args {
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the NBD server, hosting every declared export (and any mounted snapshots)
+    Serve(ServeArgs),
+    /// Take a read-only, point-in-time snapshot of a live export
+    CreateSnapshot(SnapshotTarget),
+    /// List the snapshots currently open against a live export
+    ListSnapshots(SnapshotTarget),
+    /// Delete a snapshot, freeing it to stop receiving further pre-images
+    DeleteSnapshot(DeleteSnapshotArgs),
+    /// Grow or shrink a live export
+    Resize(ResizeArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
     #[arg(short, long, default_value = "127.0.0.1")]
     host: String,
 
     #[arg(short, long, env = "SLATEDB_NBD_PORT")]
     port: Option<u16>,
 
-    #[arg(long, env = "AWS_ENDPOINT")]
-    s3_endpoint: String,
+    /// Compress each block before storing it, to cut S3 storage and egress cost
+    #[arg(long, value_enum, default_value_t = Compression::None)]
+    compression: Compression,
+
+    /// Verify each block's checksum on read; mismatches surface as I/O errors
+    #[arg(long, default_value_t = true)]
+    verify_reads: bool,
 
-    #[arg(long, env = "AWS_ALLOW_HTTP", default_value_t = false)]
-    s3_allow_http: bool,
+    /// How often to run a background scrub pass over all allocated blocks
+    #[arg(long, default_value_t = 3600)]
+    scrub_interval_secs: u64,
 
-    #[arg(long, env = "AWS_ACCESS_KEY_ID")]
-    s3_access_key_id: String,
+    /// Max number of object-store reads/writes a single request may have in flight at once
+    #[arg(long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
 
-    // Pretty sure this should always be an environment variable and you shouldn't
-    // be passing sensitive keys are as CLI arguments
-    #[arg(long, env = "AWS_SECRET_ACCESS_KEY")]
-    s3_secret_access_key: String,
+    /// Declare a named export as `name:size_bytes[:read_only]`; repeat to host
+    /// multiple exports over the same SlateDB instance. Defaults to a single
+    /// export named "default" if none are given.
+    #[arg(long = "export", value_name = "NAME:SIZE[:RO]")]
+    exports: Vec<ExportArg>,
 
-    #[arg(long, env = "AWS_BUCKET_NAME")]
-    s3_bucket_name: String,
-    // TODO Different args for different object storages
+    /// Mount an existing snapshot as an additional read-only export, as
+    /// `snapshot_id:export_name`; repeat to mount several.
+    #[arg(long = "mount-snapshot", value_name = "SNAPSHOT_ID:NAME")]
+    mounted_snapshots: Vec<MountSnapshotArg>,
+
+    #[command(subcommand)]
+    backend: Backend,
 }
 
-#[tokio::main]
-async fn main() -> std::io::Result<()> {
-    let args = Args::parse();
+/// Shared by the `create-snapshot` and `list-snapshots` subcommands: which
+/// live export to act on, and how to reach the object store that backs it.
+#[derive(clap::Args, Debug)]
+struct SnapshotTarget {
+    /// Name of the live export, as passed to `serve --export`
+    #[arg(long)]
+    export: String,
 
-    // Initialize the tracing subscriber for logging
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("slatedb_nbd=info,tokio_nbd=info"));
+    #[command(subcommand)]
+    backend: Backend,
+}
 
-    fmt::fmt()
-        .with_env_filter(env_filter)
-        .with_target(true)
-        .init();
+#[derive(clap::Args, Debug)]
+struct DeleteSnapshotArgs {
+    /// Name of the live export, as passed to `serve --export`
+    #[arg(long)]
+    export: String,
 
-    info!("Starting SlateDB NBD server");
+    /// Id of the snapshot to delete, as printed by create-snapshot/list-snapshots
+    #[arg(long)]
+    snapshot_id: u64,
 
-    // Need signal handling for graceful shutdown in production code
+    #[command(subcommand)]
+    backend: Backend,
+}
+
+#[derive(clap::Args, Debug)]
+struct ResizeArgs {
+    /// Name of the live export, as passed to `serve --export`
+    #[arg(long)]
+    export: String,
+
+    /// New device size in bytes; must be a multiple of the block size.
+    /// Growing just appends; shrinking reclaims every block beyond the new
+    /// end first
+    #[arg(long)]
+    new_size: u64,
 
+    #[command(subcommand)]
+    backend: Backend,
+}
+
+fn io_error(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, message.into())
+}
+
+async fn open_db(backend: &Backend) -> std::io::Result<Db> {
     let settings = Settings::from_env("SLATEDB_").map_err(|e| {
         error!("Failed to load SlateDB settings: {}", &e);
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to load SlateDB settings: {}", e),
-        )
+        io_error(format!("Failed to load SlateDB settings: {}", e))
     })?;
 
     info!("Using SlateDB settings: {:?}", settings);
 
-    // TODO Make object store configurable.
-    let object_store: Arc<dyn ObjectStore> = Arc::new(
-        object_store::aws::AmazonS3Builder::new()
-            // These will be different if you are using real AWS
-            .with_allow_http(args.s3_allow_http)
-            .with_endpoint(args.s3_endpoint)
-            .with_access_key_id(args.s3_access_key_id)
-            .with_secret_access_key(args.s3_secret_access_key)
-            .with_bucket_name(args.s3_bucket_name)
-            .with_conditional_put(S3ConditionalPut::ETagMatch)
-            .build()
-            .expect("failed to create object store"),
-    );
+    let object_store = build_object_store(backend)?;
 
     let db = Db::builder("/tmp/test_db", object_store)
         .build()
         .await
         .map_err(|e| {
             error!("Failed to create SlateDB: {}", e);
-            std::io::Error::new(std::io::ErrorKind::Other, "Failed to create SlateDB")
+            io_error("Failed to create SlateDB")
         })?;
 
     debug!("SlateDB instance created successfully");
-    let device = SlateDbDriver::try_from_db(db).await.map_err(|e| {
-        error!("Failed to initialize SlateDB driver: {}", e);
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Failed to initialize SlateDB driver",
+    Ok(db)
+}
+
+async fn serve(args: ServeArgs) -> std::io::Result<()> {
+    info!("Starting SlateDB NBD server");
+
+    // Need signal handling for graceful shutdown in production code
+
+    let db = open_db(&args.backend).await?;
+
+    let exports = if args.exports.is_empty() {
+        vec![ExportArg {
+            name: "default".to_string(),
+            size: DEFAULT_DEVICE_SIZE,
+            read_only: false,
+        }]
+    } else {
+        args.exports.clone()
+    };
+
+    // Export ids are derived from the export name (see `export_id_for_name`),
+    // so two exports with the same name would silently share a key range and
+    // corrupt each other's blocks. Check every declared device name -- live
+    // exports and mounted-snapshot display names alike -- for uniqueness
+    // up front, before any `SlateDbDriver` touches the database.
+    let mut device_names = std::collections::HashSet::with_capacity(
+        exports.len() + args.mounted_snapshots.len(),
+    );
+    for export in &exports {
+        if !device_names.insert(export.name.clone()) {
+            return Err(io_error(format!(
+                "Duplicate export name '{}': export names must be unique",
+                export.name
+            )));
+        }
+    }
+    for mounted in &args.mounted_snapshots {
+        let name = format!("{}@{}", mounted.export_name, mounted.snapshot_id);
+        if !device_names.insert(name.clone()) {
+            return Err(io_error(format!(
+                "Duplicate export name '{}': export and snapshot-mount names must be unique",
+                name
+            )));
+        }
+    }
+
+    let mut devices = Vec::with_capacity(exports.len() + args.mounted_snapshots.len());
+
+    for export in exports {
+        let export_id = export_id_for_name(&export.name);
+        let export_name = export.name.clone();
+        let config = ExportConfig {
+            export_id,
+            name: export.name,
+            size: export.size,
+            read_only: export.read_only,
+        };
+
+        let device = SlateDbDriver::try_from_db(
+            db.clone(),
+            config,
+            args.compression,
+            args.verify_reads,
+            args.concurrency,
         )
-    })?;
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to initialize SlateDB driver for export '{}': {}",
+                export_name, e
+            );
+            io_error("Failed to initialize SlateDB driver")
+        })?;
+
+        if args.verify_reads {
+            let scrub_config = ExportConfig {
+                export_id,
+                name: export_name.clone(),
+                size: export.size,
+                read_only: export.read_only,
+            };
+            let scrub_db = db.clone();
+            let scrub_interval = std::time::Duration::from_secs(args.scrub_interval_secs);
+            let compression = args.compression;
+            let concurrency = args.concurrency;
+
+            tokio::spawn(async move {
+                let scrub_driver = match SlateDbDriver::try_from_db(
+                    scrub_db,
+                    scrub_config,
+                    compression,
+                    true,
+                    concurrency,
+                )
+                .await
+                {
+                    Ok(driver) => driver,
+                    Err(e) => {
+                        error!(
+                            "Failed to start background scrub task for export '{}': {}",
+                            export_name, e
+                        );
+                        return;
+                    }
+                };
+
+                loop {
+                    tokio::time::sleep(scrub_interval).await;
+                    let bad_blocks = scrub_driver.scrub().await;
+                    if bad_blocks > 0 {
+                        error!(
+                            "Scrub pass for export '{}' found {} bad block(s)",
+                            export_name, bad_blocks
+                        );
+                    } else {
+                        debug!("Scrub pass for export '{}' found no checksum failures", export_name);
+                    }
+                }
+            });
+        }
+
+        devices.push(device);
+    }
+
+    for mounted in &args.mounted_snapshots {
+        let live_export_id = export_id_for_name(&mounted.export_name);
+        let live = SlateDbDriver::try_open_existing(
+            db.clone(),
+            live_export_id,
+            mounted.export_name.clone(),
+            args.compression,
+            args.verify_reads,
+            args.concurrency,
+        )
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to mount snapshot {} of export '{}': {}",
+                mounted.snapshot_id, mounted.export_name, e
+            );
+            io_error("Failed to mount snapshot")
+        })?;
+
+        let size = live.snapshot_size(mounted.snapshot_id).await.map_err(|e| {
+            error!(
+                "Failed to look up size of snapshot {} of export '{}': {:?}",
+                mounted.snapshot_id, mounted.export_name, e
+            );
+            io_error("Failed to look up snapshot size")
+        })?;
+        let name = format!("{}@{}", mounted.export_name, mounted.snapshot_id);
+        devices.push(SlateDbDriver::from_snapshot(
+            db.clone(),
+            name,
+            live_export_id,
+            mounted.snapshot_id,
+            size,
+            args.compression,
+            args.verify_reads,
+            args.concurrency,
+        ));
+    }
 
     info!("Initializing NBD server on {}", args.host);
 
     let server = NbdServerBuilder::builder()
-        .devices(vec![device])
+        .devices(devices)
         .host(&args.host)
         .maybe_port(args.port)
         .build();
@@ -113,3 +496,79 @@ async fn main() -> std::io::Result<()> {
     info!("NBD server terminated");
     Ok(())
 }
+
+async fn open_live_export(export_name: &str, backend: &Backend) -> std::io::Result<SlateDbDriver> {
+    let db = open_db(backend).await?;
+    let export_id = export_id_for_name(export_name);
+    SlateDbDriver::try_open_existing(
+        db,
+        export_id,
+        export_name.to_string(),
+        Compression::None,
+        false,
+        DEFAULT_CONCURRENCY,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to open export '{}': {}", export_name, e);
+        io_error(format!("Failed to open export '{}'", export_name))
+    })
+}
+
+async fn create_snapshot(args: SnapshotTarget) -> std::io::Result<()> {
+    let live = open_live_export(&args.export, &args.backend).await?;
+    let snapshot_id = live
+        .create_snapshot()
+        .await
+        .map_err(|e| io_error(format!("Failed to create snapshot: {:?}", e)))?;
+    println!("{}", snapshot_id);
+    Ok(())
+}
+
+async fn list_snapshots(args: SnapshotTarget) -> std::io::Result<()> {
+    let live = open_live_export(&args.export, &args.backend).await?;
+    let snapshots = live
+        .list_snapshots()
+        .await
+        .map_err(|e| io_error(format!("Failed to list snapshots: {:?}", e)))?;
+    for snapshot_id in snapshots {
+        println!("{}", snapshot_id);
+    }
+    Ok(())
+}
+
+async fn delete_snapshot(args: DeleteSnapshotArgs) -> std::io::Result<()> {
+    let live = open_live_export(&args.export, &args.backend).await?;
+    live.delete_snapshot(args.snapshot_id)
+        .await
+        .map_err(|e| io_error(format!("Failed to delete snapshot: {:?}", e)))
+}
+
+async fn resize_export(args: ResizeArgs) -> std::io::Result<()> {
+    let live = open_live_export(&args.export, &args.backend).await?;
+    live.resize(args.new_size)
+        .await
+        .map_err(|e| io_error(format!("Failed to resize export '{}': {:?}", args.export, e)))
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+
+    // Initialize the tracing subscriber for logging
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("slatedb_nbd=info,tokio_nbd=info"));
+
+    fmt::fmt()
+        .with_env_filter(env_filter)
+        .with_target(true)
+        .init();
+
+    match cli.command {
+        Command::Serve(args) => serve(args).await,
+        Command::CreateSnapshot(args) => create_snapshot(args).await,
+        Command::ListSnapshots(args) => list_snapshots(args).await,
+        Command::DeleteSnapshot(args) => delete_snapshot(args).await,
+        Command::Resize(args) => resize_export(args).await,
+    }
+}